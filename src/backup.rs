@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One branch captured by a cleanup run, enough to recreate it with
+/// `git branch <name> <oid>`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BackupEntry {
+    pub name: String,
+    pub oid: String,
+}
+
+/// A single cleanup run's backup file: the branches it deleted and when.
+pub struct Backup {
+    pub timestamp: String,
+    pub entries: Vec<BackupEntry>,
+}
+
+fn backup_dir() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-dir"])
+        .output()
+        .context("Failed to locate .git directory")?;
+
+    if !output.status.success() {
+        anyhow::bail!("Not a git repository");
+    }
+
+    let git_dir = String::from_utf8(output.stdout)?.trim().to_string();
+    Ok(Path::new(&git_dir).join("git-cleanup").join("backups"))
+}
+
+/// Incrementally persists one cleanup run's backup file as branches are
+/// deleted, so a mid-run failure still leaves already-deleted branches
+/// recoverable via `undo`.
+pub struct BackupWriter {
+    path: PathBuf,
+    entries: Vec<BackupEntry>,
+}
+
+impl BackupWriter {
+    pub fn start() -> Result<Self> {
+        let dir = backup_dir()?;
+        fs::create_dir_all(&dir).context("Failed to create backup directory")?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System clock is before the epoch")?
+            .as_secs();
+
+        Ok(Self {
+            path: dir.join(format!("{timestamp}.json")),
+            entries: Vec::new(),
+        })
+    }
+
+    /// Record a deleted branch and flush the backup file immediately.
+    pub fn record(&mut self, entry: BackupEntry) -> Result<()> {
+        self.entries.push(entry);
+        let json = serde_json::to_string_pretty(&self.entries)?;
+        fs::write(&self.path, json).context("Failed to write backup file")?;
+        Ok(())
+    }
+}
+
+/// Load recent cleanup runs, most recent first.
+pub fn list_backups() -> Result<Vec<Backup>> {
+    let dir = backup_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&dir).context("Failed to read backup directory")? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let timestamp = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        let contents = fs::read_to_string(&path)?;
+        let entries: Vec<BackupEntry> = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse backup file {}", path.display()))?;
+
+        backups.push(Backup { timestamp, entries });
+    }
+
+    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(backups)
+}
+
+/// Recreate a branch at the OID it pointed to before deletion.
+pub fn restore_branch(entry: &BackupEntry) -> Result<()> {
+    let status = Command::new("git")
+        .args(["branch", &entry.name, &entry.oid])
+        .status()
+        .context("Failed to execute git command")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to restore branch {}", entry.name);
+    }
+
+    Ok(())
+}
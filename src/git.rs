@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use git2::{BranchType, Repository, StatusOptions};
+use std::process::Command;
+
+/// Abstraction over the git operations this tool needs, so callers aren't
+/// hard-wired to shelling out to the `git` binary.
+pub trait Git {
+    fn current_branch(&self) -> Result<String>;
+    fn has_branch(&self, name: &str) -> Result<bool>;
+    fn tree_is_clean(&self) -> Result<bool>;
+    fn merged_branches(&self, target: &str) -> Result<Vec<String>>;
+    fn delete_branch(&self, name: &str, target: &str, force: bool) -> Result<()>;
+}
+
+/// Open the repository in the current directory, preferring the native
+/// `libgit2` backend and falling back to shelling out to `git` if that fails
+/// (e.g. repository layouts `git2` doesn't support yet).
+pub fn open() -> Box<dyn Git> {
+    match Git2Backend::open() {
+        Ok(backend) => Box::new(backend),
+        Err(_) => Box::new(CliBackend),
+    }
+}
+
+/// Native implementation backed by `libgit2`.
+pub struct Git2Backend {
+    repo: Repository,
+}
+
+impl Git2Backend {
+    pub fn open() -> Result<Self> {
+        let repo = Repository::discover(".").context("Not a git repository")?;
+        Ok(Self { repo })
+    }
+}
+
+impl Git for Git2Backend {
+    fn current_branch(&self) -> Result<String> {
+        let head = self.repo.head().context("Failed to read HEAD")?;
+        Ok(head.shorthand().unwrap_or("HEAD").to_string())
+    }
+
+    fn has_branch(&self, name: &str) -> Result<bool> {
+        Ok(self.repo.find_branch(name, BranchType::Local).is_ok())
+    }
+
+    fn tree_is_clean(&self) -> Result<bool> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+
+        let statuses = self
+            .repo
+            .statuses(Some(&mut opts))
+            .context("Failed to read working tree status")?;
+
+        Ok(statuses.is_empty())
+    }
+
+    fn merged_branches(&self, target: &str) -> Result<Vec<String>> {
+        let target_oid = self
+            .repo
+            .revparse_single(target)
+            .with_context(|| format!("Target branch '{target}' not found"))?
+            .peel_to_commit()?
+            .id();
+        let current = self.current_branch().ok();
+
+        let mut merged = Vec::new();
+        for branch in self.repo.branches(Some(BranchType::Local))? {
+            let (branch, _) = branch?;
+            let Some(name) = branch.name()?.map(str::to_string) else {
+                continue;
+            };
+            if name == target || Some(&name) == current.as_ref() {
+                continue;
+            }
+            let Some(branch_oid) = branch.get().target() else {
+                continue;
+            };
+
+            if branch_oid == target_oid || self.repo.graph_descendant_of(target_oid, branch_oid)? {
+                merged.push(name);
+            }
+        }
+
+        Ok(merged)
+    }
+
+    fn delete_branch(&self, name: &str, target: &str, force: bool) -> Result<()> {
+        let mut branch = self
+            .repo
+            .find_branch(name, BranchType::Local)
+            .with_context(|| format!("Branch '{name}' not found"))?;
+
+        if !force {
+            // A candidate is "merged" relative to `target`, not HEAD — the
+            // tool doesn't require the caller to be standing on `target`.
+            let target_oid = self
+                .repo
+                .revparse_single(target)
+                .with_context(|| format!("Target branch '{target}' not found"))?
+                .peel_to_commit()?
+                .id();
+            let branch_oid = branch
+                .get()
+                .target()
+                .with_context(|| format!("Branch '{name}' has no target"))?;
+            let is_merged = branch_oid == target_oid
+                || self.repo.graph_descendant_of(target_oid, branch_oid)?;
+
+            if !is_merged {
+                anyhow::bail!("Branch '{name}' is not fully merged into '{target}'");
+            }
+        }
+
+        branch
+            .delete()
+            .with_context(|| format!("Failed to delete branch '{name}'"))
+    }
+}
+
+/// Fallback implementation that shells out to the `git` binary.
+pub struct CliBackend;
+
+impl Git for CliBackend {
+    fn current_branch(&self) -> Result<String> {
+        let output = Command::new("git")
+            .args(["rev-parse", "--abbrev-ref", "HEAD"])
+            .output()
+            .context("Failed to execute git command")?;
+
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    fn has_branch(&self, name: &str) -> Result<bool> {
+        Ok(Command::new("git")
+            .args(["rev-parse", "--verify", name])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false))
+    }
+
+    fn tree_is_clean(&self) -> Result<bool> {
+        let output = Command::new("git")
+            .args(["status", "--porcelain"])
+            .output()
+            .context("Failed to execute git command")?;
+
+        Ok(output.stdout.is_empty())
+    }
+
+    fn merged_branches(&self, target: &str) -> Result<Vec<String>> {
+        let output = Command::new("git")
+            .args(["branch", "--merged", target])
+            .output()
+            .context("Failed to execute git command")?;
+
+        if !output.status.success() {
+            anyhow::bail!("Target branch '{target}' not found or not a git repository");
+        }
+
+        let output_str = String::from_utf8(output.stdout)?;
+
+        Ok(output_str
+            .lines()
+            // `*` marks the currently checked-out branch; exclude it like
+            // `Git2Backend::merged_branches` does, rather than just
+            // stripping the marker.
+            .filter(|line| !line.trim_start().starts_with('*'))
+            .map(|line| line.trim().to_string())
+            .filter(|line| line != target)
+            .collect())
+    }
+
+    fn delete_branch(&self, name: &str, _target: &str, force: bool) -> Result<()> {
+        let status = Command::new("git")
+            .arg("branch")
+            .arg(if force { "-D" } else { "-d" })
+            .arg(name)
+            .status()
+            .context("Failed to execute git command")?;
+
+        if !status.success() {
+            anyhow::bail!("Failed to delete branch '{name}'");
+        }
+
+        Ok(())
+    }
+}
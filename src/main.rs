@@ -1,13 +1,39 @@
+mod backup;
+mod git;
+mod remote;
+mod sync;
+mod table;
+
 use anyhow::{Context, Result};
-use clap::Parser;
+use backup::BackupEntry;
+use clap::{Parser, Subcommand};
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, MultiSelect};
+use dialoguer::{theme::ColorfulTheme, MultiSelect, Select};
+use git::Git;
 use std::process::Command;
+use table::BranchRow;
+use tabled::Table;
 
 /// Simple CLI tool to clean up merged git branches
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Find and delete branches already merged into the target branch
+    Clean(CleanArgs),
+    /// Fetch, fast-forward the target, and rebase local branches onto it before cleaning up
+    Sync(SyncArgs),
+    /// Restore branches deleted by a previous cleanup run
+    Undo,
+}
+
+#[derive(clap::Args, Debug)]
+struct CleanArgs {
     /// Target branch (e.g. main or master). If not provided, tries to auto-detect.
     #[arg(short, long)]
     target: Option<String>,
@@ -15,17 +41,69 @@ struct Args {
     /// Dry-run mode
     #[arg(long, default_value_t = false)]
     dry_run: bool,
+
+    /// Only pre-select branches whose newest commit is older than this many days
+    #[arg(long)]
+    stale: Option<i64>,
+
+    /// Clean up stale remote-tracking branches instead of local ones
+    #[arg(long, default_value_t = false)]
+    remote: bool,
+
+    /// Remote to use in `--remote` mode. Auto-detected if not provided.
+    #[arg(long)]
+    remote_name: Option<String>,
+
+    /// Proceed even if the working tree has uncommitted or untracked changes
+    #[arg(long, default_value_t = false)]
+    force: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct SyncArgs {
+    #[command(flatten)]
+    clean: CleanArgs,
+}
+
+/// A branch proposed for deletion, tagged with how we determined it was merged.
+struct Candidate {
+    name: String,
+    squash_detected: bool,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    match args.command {
+        Commands::Clean(clean_args) => run_clean(clean_args),
+        Commands::Sync(sync_args) => run_sync(sync_args),
+        Commands::Undo => run_undo(),
+    }
+}
+
+fn run_clean(args: CleanArgs) -> Result<()> {
+    let git = git::open();
+
+    // 0. Refuse to run against a dirty working tree unless overridden; deleting
+    // branches out from under in-flight work is how people lose commits.
+    if !args.force && !git.tree_is_clean()? {
+        eprintln!(
+            "{}",
+            "❌ Working tree has uncommitted changes. Commit/stash them or pass --force.".red()
+        );
+        return Ok(());
+    }
+
     // 1. Determine target branch (Auto-detect if not provided)
     let target = match args.target {
         Some(t) => t,
-        None => detect_default_branch()?,
+        None => detect_default_branch(git.as_ref())?,
     };
 
+    if args.remote {
+        return run_remote_cleanup(&target, args.remote_name, args.dry_run);
+    }
+
     println!(
         "{} {} {}",
         "🔍 Searching for branches merged into".blue(),
@@ -33,41 +111,64 @@ fn main() -> Result<()> {
         "..."
     );
 
-    // 2. Git: Fetch list of merged branches
-    let output = Command::new("git")
-        .arg("branch")
-        .arg("--merged")
-        .arg(&target)
-        .output()
-        .context("Failed to execute git command")?;
-
-    if !output.status.success() {
-        eprintln!("{}", "Error: Target branch not found or not a git repository.".red());
-        return Ok(());
-    }
+    // 2. Git: Fetch list of fast-forward merged branches
+    let ff_merged = git.merged_branches(&target)?;
 
-    let output_str = String::from_utf8(output.stdout)?;
+    // 2b. Catch branches that were squash- or rebase-merged, which `--merged`
+    // can't see because their tip is never an ancestor of the target.
+    let current_branch = git.current_branch().ok();
+    let squash_merged =
+        list_squash_merged_branches(&target, &ff_merged, current_branch.as_deref())?;
 
-    // 3. Parsing and filtering
-    let branches_to_clean: Vec<String> = output_str
-        .lines()
-        .map(|line| line.trim().to_string())
-        .filter(|line| !line.starts_with('*')) // Ignore current
-        .filter(|line| line != &target)        // Ignore target (main/master)
+    let mut candidates: Vec<Candidate> = ff_merged
+        .into_iter()
+        .map(|name| Candidate {
+            name,
+            squash_detected: false,
+        })
         .collect();
+    candidates.extend(squash_merged.into_iter().map(|name| Candidate {
+        name,
+        squash_detected: true,
+    }));
 
-    if branches_to_clean.is_empty() {
+    if candidates.is_empty() {
         println!("{}", "✨ Clean! No merged branches to delete.".green());
         return Ok(());
     }
 
+    // 3. Build the metadata table, sorted oldest-first so stale branches float to the top.
+    let mut rows: Vec<BranchRow> = candidates
+        .iter()
+        .map(|c| table::branch_row(&target, &c.name, c.squash_detected))
+        .collect::<Result<_>>()?;
+    rows.sort_by(|a, b| b.age_days.cmp(&a.age_days));
+    candidates.sort_by_key(|c| rows.iter().position(|row| row.name == c.name).unwrap());
+
+    println!("Found {} branches to delete:", candidates.len());
+    println!("{}", Table::new(&rows));
+
     // 4. Interactive selection (UI)
-    println!("Found {} branches to delete:", branches_to_clean.len());
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|c| {
+            if c.squash_detected {
+                format!("{} {}", c.name, "(squash-detected)".dimmed())
+            } else {
+                c.name.clone()
+            }
+        })
+        .collect();
+
+    let defaults: Vec<bool> = match args.stale {
+        Some(days) => rows.iter().map(|row| row.age_days >= days).collect(),
+        None => vec![true; candidates.len()],
+    };
 
     let selections = MultiSelect::with_theme(&ColorfulTheme::default())
         .with_prompt("Space to select/unselect, Enter to confirm")
-        .items(&branches_to_clean)
-        .defaults(&vec![true; branches_to_clean.len()])
+        .items(&labels)
+        .defaults(&defaults)
         .interact()?;
 
     if selections.is_empty() {
@@ -75,56 +176,352 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    // 5. Deletion process
+    // 5. Deletion process. The backup is flushed after each branch so a
+    // mid-loop failure still leaves already-deleted branches recoverable.
+    if args.dry_run {
+        for index in selections {
+            println!(
+                "{} {}",
+                "[Dry-Run] Would delete:".yellow(),
+                candidates[index].name
+            );
+        }
+    } else {
+        let mut backup = backup::BackupWriter::start()?;
+        for index in selections {
+            let candidate = &candidates[index];
+            let oid = branch_oid(&candidate.name)?;
+            git.delete_branch(&candidate.name, &target, candidate.squash_detected)?;
+            println!("{} {}", "🗑️  Deleted:".green(), candidate.name);
+            backup.record(BackupEntry {
+                name: candidate.name.clone(),
+                oid,
+            })?;
+        }
+        println!("{}", "Done! 🧹".green().bold());
+    }
+
+    Ok(())
+}
+
+/// Fetch, fast-forward the target, and rebase every local branch that has an
+/// upstream configured onto it, before falling through to the normal
+/// merged-branch cleanup.
+fn run_sync(args: SyncArgs) -> Result<()> {
+    let git = git::open();
+
+    if !args.clean.force && !git.tree_is_clean()? {
+        eprintln!(
+            "{}",
+            "❌ Working tree has uncommitted changes. Commit/stash them or pass --force.".red()
+        );
+        return Ok(());
+    }
+
+    let target = match &args.clean.target {
+        Some(t) => t.clone(),
+        None => detect_default_branch(git.as_ref())?,
+    };
+
+    let remote = match &args.clean.remote_name {
+        Some(r) => r.clone(),
+        None => remote::detect_remote(&target)?,
+    };
+
+    let original_branch = git.current_branch().ok();
+
+    println!(
+        "{} {} {}",
+        "🔄 Fetching and fast-forwarding".blue(),
+        target.bold(),
+        format!("from {remote}...").blue()
+    );
+    sync::fetch_and_fast_forward(&remote, &target, original_branch.as_deref())?;
+
+    let mut needs_attention = Vec::new();
+
+    for branch in sync::branches_with_upstream()? {
+        if branch == target {
+            continue;
+        }
+
+        println!("Rebasing {} onto {}...", branch.bold(), target.bold());
+        if sync::rebase_onto(&branch, &target)? {
+            println!("{}", "ok".green());
+        } else {
+            println!("{}", "conflict, aborted".red());
+            needs_attention.push(branch);
+        }
+    }
+
+    if let Some(branch) = &original_branch {
+        let status = Command::new("git")
+            .args(["checkout", branch])
+            .status()
+            .context("Failed to restore the original branch")?;
+        if !status.success() {
+            eprintln!("{} {}", "⚠️  Failed to restore original branch:".yellow(), branch);
+        }
+    }
+
+    if !needs_attention.is_empty() {
+        println!(
+            "{} {}",
+            "⚠️  Needs manual rebase:".yellow(),
+            needs_attention.join(", ")
+        );
+    }
+
+    run_clean(args.clean)
+}
+
+/// List recent cleanup runs and restore selected branches from one of them.
+fn run_undo() -> Result<()> {
+    let backups = backup::list_backups()?;
+
+    if backups.is_empty() {
+        println!("{}", "No cleanup runs to undo.".yellow());
+        return Ok(());
+    }
+
+    let run_labels: Vec<String> = backups
+        .iter()
+        .map(|b| format!("{} ({} branches)", b.timestamp, b.entries.len()))
+        .collect();
+
+    let run_index = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select a cleanup run to restore from")
+        .items(&run_labels)
+        .default(0)
+        .interact()?;
+
+    let run = &backups[run_index];
+    let branch_labels: Vec<String> = run.entries.iter().map(|e| e.name.clone()).collect();
+
+    let selections = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Space to select/unselect branches to restore, Enter to confirm")
+        .items(&branch_labels)
+        .defaults(&vec![true; run.entries.len()])
+        .interact()?;
+
+    if selections.is_empty() {
+        println!("Cancelled. No branches were restored.");
+        return Ok(());
+    }
+
     for index in selections {
-        let branch_name = &branches_to_clean[index];
+        let entry = &run.entries[index];
+        backup::restore_branch(entry)?;
+        println!("{} {}", "♻️  Restored:".green(), entry.name);
+    }
 
-        if args.dry_run {
-            println!("{} {}", "[Dry-Run] Would delete:".yellow(), branch_name);
+    Ok(())
+}
+
+/// Prune stale remote-tracking refs and offer to delete remote branches
+/// already merged into `<remote>/<target>`.
+fn run_remote_cleanup(target: &str, remote_name: Option<String>, dry_run: bool) -> Result<()> {
+    let remote = match remote_name {
+        Some(r) => r,
+        None => remote::detect_remote(target)?,
+    };
+
+    println!(
+        "{} {}",
+        "🔍 Pruning stale remote-tracking branches for".blue(),
+        remote.bold()
+    );
+    remote::prune_remote(&remote)?;
+
+    let branches = remote::list_remote_merged_branches(&remote, target)?;
+
+    if branches.is_empty() {
+        println!("{}", "✨ Clean! No merged remote branches to delete.".green());
+        return Ok(());
+    }
+
+    let mut rows: Vec<BranchRow> = branches
+        .iter()
+        .map(|b| {
+            let remote_ref = format!("{remote}/{b}");
+            let remote_target = format!("{remote}/{target}");
+            let mut row = table::branch_row(&remote_target, &remote_ref, false)?;
+            row.name = b.clone();
+            Ok(row)
+        })
+        .collect::<Result<_>>()?;
+    rows.sort_by(|a, b| b.age_days.cmp(&a.age_days));
+
+    let labels: Vec<String> = rows.iter().map(|row| row.name.clone()).collect();
+
+    println!("Found {} remote branches to delete:", labels.len());
+    println!("{}", Table::new(&rows));
+
+    let selections = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Space to select/unselect, Enter to confirm")
+        .items(&labels)
+        .defaults(&vec![true; labels.len()])
+        .interact()?;
+
+    if selections.is_empty() {
+        println!("Cancelled. No remote branches were deleted.");
+        return Ok(());
+    }
+
+    for index in selections {
+        let branch = &labels[index];
+
+        if dry_run {
+            println!("{} {}/{}", "[Dry-Run] Would delete:".yellow(), remote, branch);
         } else {
-            delete_branch(branch_name)?;
+            remote::delete_remote_branch(&remote, branch)?;
         }
     }
 
-    if !args.dry_run {
+    if !dry_run {
         println!("{}", "Done! 🧹".green().bold());
     }
 
     Ok(())
 }
 
-/// Helper to check if branch exists
-fn branch_exists(name: &str) -> bool {
-    Command::new("git")
-        .args(["rev-parse", "--verify", name])
+/// Branches that are effectively merged into `target` via a squash or rebase,
+/// so their tip commit never shows up as an ancestor of `target`.
+///
+/// For each local branch not already covered by `--merged`, we synthesize a
+/// single squashed commit from the branch's tree and check whether its
+/// patch-id is already present in `target` via `git cherry`.
+fn list_squash_merged_branches(
+    target: &str,
+    already_merged: &[String],
+    current_branch: Option<&str>,
+) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .arg("branch")
+        .arg("--list")
+        .arg("--format=%(refname:short)")
         .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+        .context("Failed to list local branches")?;
+
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let output_str = String::from_utf8(output.stdout)?;
+
+    let mut squash_merged = Vec::new();
+
+    for branch in output_str.lines().map(|l| l.trim()) {
+        if branch.is_empty()
+            || branch == target
+            || Some(branch) == current_branch
+            || already_merged.iter().any(|b| b == branch)
+        {
+            continue;
+        }
+
+        if is_squash_merged(target, branch)? {
+            squash_merged.push(branch.to_string());
+        }
+    }
+
+    Ok(squash_merged)
+}
+
+fn is_squash_merged(target: &str, branch: &str) -> Result<bool> {
+    let base_output = Command::new("git")
+        .args(["merge-base", target, branch])
+        .output()
+        .context("Failed to compute merge-base")?;
+
+    if !base_output.status.success() {
+        return Ok(false);
+    }
+    let base = String::from_utf8(base_output.stdout)?.trim().to_string();
+
+    let tip_output = Command::new("git")
+        .args(["rev-parse", branch])
+        .output()
+        .context("Failed to resolve branch tip")?;
+    let tip = String::from_utf8(tip_output.stdout)?.trim().to_string();
+
+    if tip == base {
+        // Already an ancestor of target; `--merged` already covers this.
+        return Ok(false);
+    }
+
+    let tree_output = Command::new("git")
+        .args(["rev-parse", &format!("{branch}^{{tree}}")])
+        .output()
+        .context("Failed to resolve branch tree")?;
+    let tree = String::from_utf8(tree_output.stdout)?.trim().to_string();
+
+    let synthetic_output = Command::new("git")
+        .args(["commit-tree", &tree, "-p", &base, "-m", "_"])
+        .output()
+        .context("Failed to synthesize squashed commit")?;
+
+    if !synthetic_output.status.success() {
+        return Ok(false);
+    }
+    let synthetic = String::from_utf8(synthetic_output.stdout)?.trim().to_string();
+
+    let cherry_output = Command::new("git")
+        .args(["cherry", target, &synthetic])
+        .output()
+        .context("Failed to run git cherry")?;
+
+    Ok(cherry_indicates_merged(&String::from_utf8(
+        cherry_output.stdout,
+    )?))
+}
+
+/// `git cherry <target> <synthetic-commit>` prints a single line for our
+/// one-commit synthetic range; a leading `-` means its patch-id is already
+/// present in `target`, i.e. the branch's net changes are merged.
+fn cherry_indicates_merged(cherry_output: &str) -> bool {
+    let line = cherry_output.trim();
+    !line.is_empty() && line.starts_with('-')
+}
+
+/// Resolve a branch name to its current tip OID.
+fn branch_oid(name: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", name])
+        .output()
+        .context("Failed to resolve branch tip")?;
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
 }
 
 /// Logic to find main or master
-fn detect_default_branch() -> Result<String> {
-    if branch_exists("main") {
+fn detect_default_branch(git: &dyn Git) -> Result<String> {
+    if git.has_branch("main")? {
         return Ok("main".to_string());
     }
-    if branch_exists("master") {
+    if git.has_branch("master")? {
         return Ok("master".to_string());
     }
     // Fallback if neither exists (unlikely but possible)
     Ok("main".to_string())
 }
 
-fn delete_branch(branch_name: &str) -> Result<()> {
-    let status = Command::new("git")
-        .arg("branch")
-        .arg("-d")
-        .arg(branch_name)
-        .status()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if status.success() {
-        println!("{} {}", "🗑️  Deleted:".green(), branch_name);
-    } else {
-        eprintln!("{} {}", "❌ Error deleting:".red(), branch_name);
+    #[test]
+    fn cherry_indicates_merged_on_leading_dash() {
+        assert!(cherry_indicates_merged("- deadbeef0123 _\n"));
+    }
+
+    #[test]
+    fn cherry_indicates_merged_false_on_leading_plus() {
+        assert!(!cherry_indicates_merged("+ deadbeef0123 _\n"));
+    }
+
+    #[test]
+    fn cherry_indicates_merged_false_on_empty_output() {
+        assert!(!cherry_indicates_merged(""));
     }
-    Ok(())
 }
\ No newline at end of file
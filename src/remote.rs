@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Pick the remote to operate on when the user didn't pass `--remote-name`:
+/// the remote `target` actually tracks (the repository's configured
+/// upstream), falling back to a name guess (`upstream`, else `origin`, else
+/// the sole configured remote) when `target` has no tracking info.
+pub fn detect_remote(target: &str) -> Result<String> {
+    if let Some(remote) = tracked_remote(target)? {
+        return Ok(remote);
+    }
+
+    let output = Command::new("git")
+        .args(["remote", "-v"])
+        .output()
+        .context("Failed to list remotes")?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to list remotes");
+    }
+
+    let output_str = String::from_utf8(output.stdout)?;
+    let remotes = parse_fetch_remotes(&output_str);
+
+    pick_remote(&remotes).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Could not determine which remote to use; pass --remote-name explicitly (found: {})",
+            remotes.join(", ")
+        )
+    })
+}
+
+/// The remote `target` is configured to track, i.e. the repository's actual
+/// upstream for that branch, per `branch.<target>.remote`. `None` if
+/// `target` has no tracking configuration.
+fn tracked_remote(target: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(upstream:remotename)",
+            &format!("refs/heads/{target}"),
+        ])
+        .output()
+        .context("Failed to read branch tracking configuration")?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let name = String::from_utf8(output.stdout)?.trim().to_string();
+    Ok(if name.is_empty() { None } else { Some(name) })
+}
+
+/// Remote names with a `(fetch)` URL from `git remote -v` output, in the
+/// order they appear.
+fn parse_fetch_remotes(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| line.trim_end().ends_with("(fetch)"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Prefer `upstream`, else `origin`, else the sole remote; `None` if there's
+/// more than one remote and neither preferred name is present.
+fn pick_remote(remotes: &[String]) -> Option<String> {
+    if remotes.iter().any(|r| r == "upstream") {
+        return Some("upstream".to_string());
+    }
+    if remotes.iter().any(|r| r == "origin") {
+        return Some("origin".to_string());
+    }
+    if let [only] = remotes {
+        return Some(only.clone());
+    }
+    None
+}
+
+/// Remove stale remote-tracking refs for branches deleted upstream.
+pub fn prune_remote(remote: &str) -> Result<()> {
+    let status = Command::new("git")
+        .args(["remote", "prune", remote])
+        .status()
+        .context("Failed to prune remote-tracking branches")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to prune remote '{remote}'");
+    }
+
+    Ok(())
+}
+
+/// Remote branches (short names, without the `<remote>/` prefix) already
+/// merged into `<remote>/<target>`.
+pub fn list_remote_merged_branches(remote: &str, target: &str) -> Result<Vec<String>> {
+    let remote_target = format!("{remote}/{target}");
+
+    let output = Command::new("git")
+        .args(["branch", "-r", "--merged", &remote_target])
+        .output()
+        .context("Failed to list remote branches")?;
+
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let output_str = String::from_utf8(output.stdout)?;
+    Ok(parse_remote_merged_branches(&output_str, remote, target))
+}
+
+/// Strip the `<remote>/` prefix from `git branch -r --merged` output, and
+/// drop the `<remote>/HEAD -> <remote>/<target>` pointer line and `target`
+/// itself.
+fn parse_remote_merged_branches(output: &str, remote: &str, target: &str) -> Vec<String> {
+    let prefix = format!("{remote}/");
+
+    output
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.contains("->")) // skip origin/HEAD -> origin/main
+        .filter_map(|line| line.strip_prefix(&prefix).map(|s| s.to_string()))
+        .filter(|name| name != target)
+        .collect()
+}
+
+/// Delete a branch on the remote.
+pub fn delete_remote_branch(remote: &str, branch: &str) -> Result<()> {
+    let status = Command::new("git")
+        .args(["push", remote, "--delete", branch])
+        .status()
+        .context("Failed to execute git push")?;
+
+    if status.success() {
+        println!("Deleted remote branch: {remote}/{branch}");
+    } else {
+        eprintln!("Error deleting remote branch: {remote}/{branch}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn pick_remote_prefers_upstream() {
+        let remotes = names(&["origin", "upstream", "fork"]);
+        assert_eq!(pick_remote(&remotes), Some("upstream".to_string()));
+    }
+
+    #[test]
+    fn pick_remote_falls_back_to_origin() {
+        let remotes = names(&["fork", "origin"]);
+        assert_eq!(pick_remote(&remotes), Some("origin".to_string()));
+    }
+
+    #[test]
+    fn pick_remote_falls_back_to_sole_remote() {
+        let remotes = names(&["fork"]);
+        assert_eq!(pick_remote(&remotes), Some("fork".to_string()));
+    }
+
+    #[test]
+    fn pick_remote_ambiguous_returns_none() {
+        let remotes = names(&["fork", "mirror"]);
+        assert_eq!(pick_remote(&remotes), None);
+    }
+
+    #[test]
+    fn parse_remote_merged_branches_strips_prefix_and_head_pointer() {
+        let output = "  origin/HEAD -> origin/main\n  origin/main\n  origin/feature-a\n";
+        let branches = parse_remote_merged_branches(output, "origin", "main");
+        assert_eq!(branches, vec!["feature-a".to_string()]);
+    }
+
+    #[test]
+    fn parse_fetch_remotes_keeps_only_fetch_lines() {
+        let output = "origin\thttps://example.com/repo.git (fetch)\n\
+                       origin\thttps://example.com/repo.git (push)\n\
+                       upstream\thttps://example.com/upstream.git (fetch)\n\
+                       upstream\thttps://example.com/upstream.git (push)\n";
+        assert_eq!(
+            parse_fetch_remotes(output),
+            vec!["origin".to_string(), "upstream".to_string()]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Local branches that have an upstream configured (`git branch -u ...`),
+/// i.e. ones worth rebasing onto a freshly-updated target.
+pub fn branches_with_upstream() -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname:short)\t%(upstream:short)",
+            "refs/heads/",
+        ])
+        .output()
+        .context("Failed to list local branches")?;
+
+    let output_str = String::from_utf8(output.stdout)?;
+
+    Ok(output_str
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '\t');
+            let name = parts.next()?.trim();
+            let upstream = parts.next().unwrap_or("").trim();
+            if name.is_empty() || upstream.is_empty() {
+                None
+            } else {
+                Some(name.to_string())
+            }
+        })
+        .collect())
+}
+
+/// Fetch `remote` and fast-forward the local `target` branch to match
+/// `<remote>/<target>`.
+///
+/// Git refuses a `<target>:<target>` fetch refspec when `target` is the
+/// currently checked-out branch, so that case is fast-forwarded in the
+/// working tree instead via `merge --ff-only`.
+pub fn fetch_and_fast_forward(remote: &str, target: &str, current_branch: Option<&str>) -> Result<()> {
+    if current_branch == Some(target) {
+        let status = Command::new("git")
+            .args(["fetch", remote, target])
+            .status()
+            .context("Failed to execute git fetch")?;
+        if !status.success() {
+            anyhow::bail!("Failed to fetch '{target}' from '{remote}'");
+        }
+
+        let status = Command::new("git")
+            .args(["merge", "--ff-only", &format!("{remote}/{target}")])
+            .status()
+            .context("Failed to execute git merge")?;
+        if !status.success() {
+            anyhow::bail!("Failed to fast-forward '{target}' from '{remote}/{target}'");
+        }
+
+        return Ok(());
+    }
+
+    let status = Command::new("git")
+        .args(["fetch", remote, &format!("{target}:{target}")])
+        .status()
+        .context("Failed to execute git fetch")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to fast-forward '{target}' from '{remote}/{target}'");
+    }
+
+    Ok(())
+}
+
+/// Rebase `branch` onto `onto`, aborting cleanly on conflict instead of
+/// leaving the repository mid-rebase.
+///
+/// Returns `true` if the rebase completed, `false` if it was aborted and
+/// needs manual attention.
+pub fn rebase_onto(branch: &str, onto: &str) -> Result<bool> {
+    let status = Command::new("git")
+        .args(["rebase", onto, branch])
+        .status()
+        .context("Failed to execute git rebase")?;
+
+    if status.success() {
+        return Ok(true);
+    }
+
+    Command::new("git")
+        .args(["rebase", "--abort"])
+        .status()
+        .context("Failed to abort git rebase")?;
+
+    Ok(false)
+}
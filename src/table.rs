@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tabled::Tabled;
+
+/// Everything we show the user before they decide whether a branch is safe
+/// to delete.
+#[derive(Tabled)]
+pub struct BranchRow {
+    #[tabled(rename = "Branch")]
+    pub name: String,
+    #[tabled(rename = "Last Commit")]
+    pub last_commit: String,
+    #[tabled(rename = "Author")]
+    pub author: String,
+    #[tabled(rename = "Ahead")]
+    pub ahead: u32,
+    #[tabled(rename = "Behind")]
+    pub behind: u32,
+    #[tabled(rename = "Merged Via")]
+    pub merged_via: String,
+
+    /// Age of the last commit in days, used for `--stale` filtering and sorting.
+    /// Not rendered as a column.
+    #[tabled(skip)]
+    pub age_days: i64,
+}
+
+/// Gather the row of metadata shown for a single branch candidate.
+pub fn branch_row(target: &str, name: &str, squash_detected: bool) -> Result<BranchRow> {
+    let log_output = Command::new("git")
+        .args(["log", "-1", "--format=%ci\t%an\t%ct", name])
+        .output()
+        .context("Failed to read branch commit info")?;
+
+    let log_str = String::from_utf8(log_output.stdout)?;
+    let mut fields = log_str.trim().splitn(3, '\t');
+    let last_commit = fields.next().unwrap_or("unknown").to_string();
+    let author = fields.next().unwrap_or("unknown").to_string();
+    let committed_at: i64 = fields.next().unwrap_or("0").parse().unwrap_or(0);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System clock is before the epoch")?
+        .as_secs() as i64;
+    let age_days = (now - committed_at) / 86_400;
+
+    let (ahead, behind) = ahead_behind(target, name)?;
+
+    Ok(BranchRow {
+        name: name.to_string(),
+        last_commit,
+        author,
+        ahead,
+        behind,
+        merged_via: if squash_detected {
+            "squash".to_string()
+        } else {
+            "fast-forward".to_string()
+        },
+        age_days,
+    })
+}
+
+/// Commits `branch` has that `target` doesn't (ahead), and vice versa (behind).
+fn ahead_behind(target: &str, branch: &str) -> Result<(u32, u32)> {
+    let output = Command::new("git")
+        .args([
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("{target}...{branch}"),
+        ])
+        .output()
+        .context("Failed to compute ahead/behind counts")?;
+
+    let output_str = String::from_utf8(output.stdout)?;
+    Ok(parse_left_right_counts(&output_str))
+}
+
+/// Parse `git rev-list --left-right --count <target>...<branch>` output into
+/// `(ahead, behind)`. The left column counts commits only in `target`
+/// (`branch` is behind by that many); the right column counts commits only
+/// in `branch` (`branch` is ahead by that many).
+fn parse_left_right_counts(output: &str) -> (u32, u32) {
+    let mut counts = output.split_whitespace();
+    let behind: u32 = counts.next().unwrap_or("0").parse().unwrap_or(0);
+    let ahead: u32 = counts.next().unwrap_or("0").parse().unwrap_or(0);
+
+    (ahead, behind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_left_right_counts_maps_left_to_behind_right_to_ahead() {
+        // left-right count "<target>...<branch>": left = target-only (behind),
+        // right = branch-only (ahead).
+        assert_eq!(parse_left_right_counts("3\t5\n"), (5, 3));
+    }
+
+    #[test]
+    fn parse_left_right_counts_defaults_to_zero_on_empty_output() {
+        assert_eq!(parse_left_right_counts(""), (0, 0));
+    }
+}